@@ -9,5 +9,7 @@ pub mod file;
 pub mod block;
 pub mod disk;
 pub mod fs;
+pub mod secure_block;
 pub mod security;
+pub mod security_cmd;
 pub mod partition;
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Discovery helper for pairing block devices with a co-located
+//! `StorageSecurityCommand` instance.
+
+use alloc::vec::Vec;
+
+use crate::Result;
+use crate::boot::{self, ScopedProtocol, SearchType};
+use crate::proto::media::block::BlockIO;
+use crate::proto::media::security_cmd::StorageSecurityCommand;
+
+/// Finds all device handles that expose both `BlockIO` and
+/// `StorageSecurityCommand`.
+///
+/// EDK2 only installs `EFI_STORAGE_SECURITY_COMMAND_PROTOCOL` on a handle
+/// when the underlying ATA/NVMe device advertises trusted-computing
+/// support, so in practice the two protocols coexist on the same handle
+/// rather than `StorageSecurityCommand` standing alone. This scans every
+/// `BlockIO` handle and pairs up the ones that also support
+/// `StorageSecurityCommand`, so callers don't have to locate and match up
+/// the two protocols themselves.
+///
+/// # Errors
+///
+/// Returns an error if the `BlockIO` handle list cannot be retrieved.
+pub fn find_secure_block_devices()
+-> Result<Vec<(ScopedProtocol<BlockIO>, ScopedProtocol<StorageSecurityCommand>)>> {
+    let handles = boot::locate_handle_buffer(SearchType::from_proto::<BlockIO>())?;
+
+    let mut devices = Vec::new();
+    for handle in handles.iter().copied() {
+        let Ok(security) = boot::open_protocol_exclusive::<StorageSecurityCommand>(handle) else {
+            continue;
+        };
+        let Ok(block_io) = boot::open_protocol_exclusive::<BlockIO>(handle) else {
+            continue;
+        };
+        devices.push((block_io, security));
+    }
+
+    Ok(devices)
+}
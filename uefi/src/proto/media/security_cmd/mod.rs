@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! StorageSecurityCommand protocol.
+
+pub mod opal;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::proto::media::block::BlockIO;
+use crate::proto::unsafe_protocol;
+use crate::{Handle, Result, Status, StatusExt, boot};
+use uefi_raw::protocol::media::StorageSecurityCommandProtocol;
+
+/// Identifies the security protocol a [`StorageSecurityCommand::send_data`]
+/// or [`StorageSecurityCommand::receive_data`] call targets.
+///
+/// These are the SPC-4 / UEFI-assigned "SECURITY PROTOCOL" values; see the
+/// `EFI_STORAGE_SECURITY_COMMAND_PROTOCOL` section of the UEFI
+/// Specification and the SPC-4 `SECURITY PROTOCOL IN` command description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SecurityProtocolId {
+    /// Security protocol information (discovery), protocol `0x00`.
+    Information,
+    /// A TCG-defined security protocol, protocol `0x01`-`0x06`.
+    Tcg(u8),
+    /// T10-reserved, protocol `0x20`.
+    T10Reserved,
+    /// IEEE 1667, protocol `0xEA`.
+    Ieee1667,
+    /// ATA device server password security, protocol `0xEE`.
+    AtaDeviceServerPassword,
+    /// SED / TCG-style ATA security, protocol `0xEF`.
+    AtaSecurity,
+    /// Any other, unrecognized protocol value.
+    Other(u8),
+}
+
+impl From<u8> for SecurityProtocolId {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Information,
+            0x01..=0x06 => Self::Tcg(value),
+            0x20 => Self::T10Reserved,
+            0xEA => Self::Ieee1667,
+            0xEE => Self::AtaDeviceServerPassword,
+            0xEF => Self::AtaSecurity,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<SecurityProtocolId> for u8 {
+    fn from(value: SecurityProtocolId) -> Self {
+        match value {
+            SecurityProtocolId::Information => 0x00,
+            SecurityProtocolId::Tcg(protocol) => protocol,
+            SecurityProtocolId::T10Reserved => 0x20,
+            SecurityProtocolId::Ieee1667 => 0xEA,
+            SecurityProtocolId::AtaDeviceServerPassword => 0xEE,
+            SecurityProtocolId::AtaSecurity => 0xEF,
+            SecurityProtocolId::Other(protocol) => protocol,
+        }
+    }
+}
+
+/// Outcome of a successful [`StorageSecurityCommand::receive_data`] call.
+#[derive(Debug)]
+pub enum ReceiveData<'a> {
+    /// The full response was received.
+    Data(&'a [u8]),
+    /// The device reported `EFI_WARN_BUFFER_TOO_SMALL`: the supplied buffer
+    /// wasn't large enough to hold the full response.
+    BufferTooSmall {
+        /// The (possibly truncated) data the device wrote to the buffer.
+        data: &'a [u8],
+        /// The transfer size the device reports is required to receive the
+        /// full response.
+        required_size: usize,
+    },
+}
+
+/// Storage Security Command [`Protocol`].
+///
+/// Used to abstract sending and receiving security protocol commands to
+/// storage devices.
+///
+/// # UEFI Spec Description
+/// This protocol is used to abstract mass storage devices to allow code
+/// running in the EFI boot services environment to send security protocol
+/// commands to mass storage devices without specific knowledge of the type
+/// of device or controller that manages the device. Functions are defined
+/// to send or retrieve security protocol defined data to and from mass
+/// storage devices. This protocol shall be supported on all physical and
+/// logical storage devices supporting the EFI_BLOCK_IO_PROTOCOL or
+/// EFI_BLOCK_IO2_PROTOCOL in the EFI boot services environment and one of
+/// the following command sets (or their alternative) at the bus level:
+///
+/// * TRUSTED SEND/RECEIVE commands of the ATA8-ACS command set or its successor
+/// * SECURITY PROTOCOL IN/OUT commands of the SPC-4 command set or its successor.
+///
+/// If the mass storage device is part of a RAID set, the specific physical device
+/// may not support the block IO protocols directly, but they are supported by
+/// the logical device defining the RAID set. In this case the MediaId parameter
+/// may not be available and its value is undefined for this interface.
+///
+/// [`Protocol`]: uefi::proto::Protocol
+#[derive(Debug)]
+#[repr(transparent)]
+#[unsafe_protocol(StorageSecurityCommandProtocol::GUID)]
+pub struct StorageSecurityCommand(StorageSecurityCommandProtocol);
+
+impl StorageSecurityCommand {
+    /// Receive data and/or the result of one or more commands sent by `send_data()`.
+    ///
+    /// # Errors
+    ///
+    /// See section `EFI_STORAGE_SECURITY_COMMAND_PROTOCOL.ReceiveData()` in the UEFI Specification
+    /// for details.
+    ///
+    /// * [`Status::UNSUPPORTED`]
+    /// * [`Status::DEVICE_ERROR`]
+    /// * [`Status::NO_MEDIA`]
+    /// * [`Status::MEDIA_CHANGED`]
+    /// * [`Status::INVALID_PARAMETER`]
+    /// * [`Status::TIMEOUT`]
+    pub fn receive_data<'a>(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        protocol: impl Into<SecurityProtocolId>,
+        protocol_specific: u16,
+        data: &'a mut [u8],
+    ) -> Result<ReceiveData<'a>> {
+        let protocol: u8 = protocol.into().into();
+        let mut actual_size: usize = 0;
+
+        let status = unsafe {
+            (self.0.receive_data)(
+                &mut self.0,
+                media_id,
+                timeout,
+                protocol,
+                protocol_specific,
+                data.len(),
+                data.as_mut_ptr().cast(),
+                &mut actual_size,
+            )
+        };
+
+        // `EFI_WARN_BUFFER_TOO_SMALL` is a warning code, not an error: the
+        // spec defines it as "the buffer... was too small", with
+        // `TransferLengthOut` updated to the size of buffer needed. Surface
+        // that distinctly rather than folding it into the generic success
+        // path.
+        if status == Status::WARN_BUFFER_TOO_SMALL {
+            return Ok(ReceiveData::BufferTooSmall {
+                data,
+                required_size: actual_size,
+            });
+        }
+
+        status.to_result_with_val(|| ReceiveData::Data(&data[..actual_size]))
+    }
+
+    /// Send a security protocol command to a device.
+    ///
+    /// # Errors
+    ///
+    /// See section `EFI_STORAGE_SECURITY_COMMAND_PROTOCOL.SendData()` in the UEFI Specification
+    /// for details.
+    ///
+    /// * [`Status::UNSUPPORTED`]
+    /// * [`Status::DEVICE_ERROR`]
+    /// * [`Status::NO_MEDIA`]
+    /// * [`Status::MEDIA_CHANGED`]
+    /// * [`Status::INVALID_PARAMETER`]
+    /// * [`Status::TIMEOUT`]
+    pub fn send_data(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        protocol: impl Into<SecurityProtocolId>,
+        protocol_specific: u16,
+        data: &[u8],
+    ) -> Result {
+        let protocol: u8 = protocol.into().into();
+        unsafe {
+            (self.0.send_data)(
+                &mut self.0,
+                media_id,
+                timeout,
+                protocol,
+                protocol_specific,
+                data.len(),
+                data.as_ptr().cast(),
+            )
+        }
+        .to_result()
+    }
+
+    /// Calls [`Self::receive_data`], automatically growing `buf` and
+    /// retrying once if the device reports
+    /// [`ReceiveData::BufferTooSmall`].
+    fn receive_data_grow<'a>(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        protocol: impl Into<SecurityProtocolId>,
+        protocol_specific: u16,
+        buf: &'a mut Vec<u8>,
+    ) -> Result<&'a [u8]> {
+        let protocol = protocol.into();
+
+        let len = match self.receive_data(media_id, timeout, protocol, protocol_specific, &mut *buf)?
+        {
+            ReceiveData::Data(data) => data.len(),
+            ReceiveData::BufferTooSmall { required_size, .. } => {
+                buf.resize(required_size, 0);
+                match self.receive_data(media_id, timeout, protocol, protocol_specific, &mut *buf)? {
+                    ReceiveData::Data(data) => data.len(),
+                    // The device is still reporting a larger size than what
+                    // it just told us to grow to; trust the buffer it wrote.
+                    ReceiveData::BufferTooSmall { data, .. } => data.len(),
+                }
+            }
+        };
+
+        Ok(&buf[..len])
+    }
+
+    /// Returns the list of security protocols supported by the device.
+    ///
+    /// This issues the standardized "supported security protocols" inquiry
+    /// (security protocol `0x00`), which is the common entry point for
+    /// discovering whether a device speaks TCG, IEEE 1667, or one of the
+    /// other security protocol command sets before attempting to use them.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::receive_data`].
+    pub fn security_protocol_list(&mut self, media_id: u32, timeout: u64) -> Result<Vec<u8>> {
+        // A small buffer covers the common case (a handful of supported
+        // protocols) in a single round trip.
+        let mut buf = vec![0u8; 64];
+        let data = self.receive_data_grow(
+            media_id,
+            timeout,
+            SecurityProtocolId::Information,
+            0x0000,
+            &mut buf,
+        )?;
+
+        if data.len() < 4 {
+            return Err(Status::DEVICE_ERROR.into());
+        }
+        let list_len = u16::from_be_bytes([data[2], data[3]]) as usize;
+        let list_len = list_len.min(data.len() - 4);
+
+        Ok(data[4..4 + list_len].to_vec())
+    }
+
+    /// Returns the `media_id` to use in `receive_data`/`send_data` calls
+    /// against the `BlockIO` device co-located with this protocol instance
+    /// on `handle`.
+    ///
+    /// EDK2 only installs this protocol on a handle when the underlying
+    /// ATA/NVMe device advertises trusted-computing support, and it does so
+    /// on the device's `BlockIO` handle, so the two normally coexist on the
+    /// same handle. See also
+    /// [`find_secure_block_devices`](super::secure_block::find_secure_block_devices).
+    ///
+    /// Returns `None` if `handle` has no `BlockIO` protocol, or if the
+    /// device has no media present (as can happen for the RAID logical
+    /// device case the UEFI Specification notes, where `media_id` is
+    /// undefined for this interface).
+    #[must_use]
+    pub fn media_id_for(handle: Handle) -> Option<u32> {
+        let block_io = boot::open_protocol_exclusive::<BlockIO>(handle).ok()?;
+        let media = block_io.media();
+        media.media_present().then(|| media.media_id())
+    }
+}
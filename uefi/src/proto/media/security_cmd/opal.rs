@@ -0,0 +1,631 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! TCG Opal / self-encrypting-drive unlock support.
+//!
+//! This module layers the TCG Storage "Security Subsystem Class" (SSC)
+//! session protocol on top of the raw [`StorageSecurityCommand`] IF-SEND /
+//! IF-RECV primitives, so that a locked Opal (or Pyrite) self-encrypting
+//! drive can be unlocked. This is the primary real-world consumer of SSC:
+//! EDK2 only installs `EFI_STORAGE_SECURITY_COMMAND_PROTOCOL` on a handle
+//! when the underlying ATA IDENTIFY DEVICE data reports the
+//! `trusted_computing_support` bit.
+//!
+//! Only the handful of features needed to drive a basic unlock are
+//! implemented: Level 0 Discovery, starting an authenticated session with
+//! the Locking SP, and clearing the read/write lock on `LockingRange0`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{SecurityProtocolId, StorageSecurityCommand};
+use crate::{Result, Status};
+
+/// Security protocol used for all TCG Storage traffic (discovery and
+/// ComID-addressed session traffic alike).
+const SECURITY_PROTOCOL_TCG: SecurityProtocolId = SecurityProtocolId::Tcg(0x01);
+
+/// `protocol_specific` value requesting TCG Level 0 Discovery.
+const LEVEL_0_DISCOVERY: u16 = 0x0001;
+
+/// Feature code of the Opal SSC (v2) Level 0 Discovery feature descriptor.
+const FEATURE_CODE_OPAL_SSC: u16 = 0x0203;
+
+/// Feature code of the Pyrite SSC v1 Level 0 Discovery feature descriptor.
+const FEATURE_CODE_PYRITE_SSC_V1: u16 = 0x0302;
+
+/// Feature code of the Pyrite SSC v2 Level 0 Discovery feature descriptor.
+const FEATURE_CODE_PYRITE_SSC_V2: u16 = 0x0303;
+
+/// Feature code of the locking feature descriptor.
+const FEATURE_CODE_LOCKING: u16 = 0x0002;
+
+/// Length, in bytes, of the Level 0 Discovery header that precedes the
+/// feature descriptors.
+const DISCOVERY_HEADER_LEN: usize = 48;
+
+/// `LockingEnabled` bit within a locking feature descriptor's status byte.
+const LOCKING_ENABLED: u8 = 0b0000_0010;
+
+/// `Locked` bit within a locking feature descriptor's status byte.
+const LOCKED: u8 = 0b0000_0100;
+
+/// Host session number used for the Locking SP session opened by
+/// [`StorageSecurityCommand::unlock`]. Arbitrary non-zero value.
+const HOST_SESSION_ID: u8 = 1;
+
+/// Well-known TCG security provider (SP) and authority UIDs used by the
+/// Opal SSC.
+mod uid {
+    /// `SMUID`, the invoking UID for session-management methods such as
+    /// `StartSession`.
+    pub const SMUID: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x01];
+    /// `StartSession` method UID.
+    pub const START_SESSION: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x02];
+    /// `Set` method UID.
+    pub const SET: [u8; 8] = [0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x17];
+    /// `LockingSP` security provider UID.
+    pub const LOCKING_SP: [u8; 8] = [0x00, 0x00, 0x02, 0x05, 0x00, 0x00, 0x00, 0x02];
+    /// `Admin1` authority within the Locking SP.
+    pub const ADMIN1: [u8; 8] = [0x00, 0x00, 0x00, 0x09, 0x00, 0x01, 0x00, 0x01];
+    /// `User1` authority within the Locking SP.
+    pub const USER1: [u8; 8] = [0x00, 0x00, 0x00, 0x09, 0x00, 0x03, 0x00, 0x01];
+    /// `LockingRange0` object UID.
+    pub const LOCKING_RANGE0: [u8; 8] = [0x00, 0x00, 0x08, 0x02, 0x00, 0x00, 0x00, 0x01];
+}
+
+/// Authority to authenticate as when starting a session with the Locking
+/// SP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authority {
+    /// The `Admin1` authority.
+    Admin1,
+    /// The `User1` authority.
+    User1,
+}
+
+impl Authority {
+    fn uid(self) -> [u8; 8] {
+        match self {
+            Self::Admin1 => uid::ADMIN1,
+            Self::User1 => uid::USER1,
+        }
+    }
+}
+
+/// Parsed subset of a device's TCG Level 0 Discovery response.
+#[derive(Debug, Clone, Copy)]
+pub struct Discovery {
+    /// Base ComID assigned to the Opal (or Pyrite) SSC, used for all further IF-SEND /
+    /// IF-RECV traffic.
+    pub base_com_id: u16,
+    /// Whether the locking feature is enabled on this device.
+    pub locking_enabled: bool,
+    /// Whether the device is currently locked.
+    pub locked: bool,
+}
+
+/// Simple TCG "stream" tokens used to encode method calls.
+///
+/// See the TCG Storage Architecture Core Specification's description of
+/// simple and atom tokens.
+mod token {
+    pub const START_LIST: u8 = 0xF0;
+    pub const END_LIST: u8 = 0xF1;
+    pub const CALL: u8 = 0xF8;
+    pub const END_OF_DATA: u8 = 0xF9;
+    pub const END_OF_SESSION: u8 = 0xFA;
+
+    /// The empty method status list (`StartList, 0 (status code),
+    /// EndList`) every host-sent method call must end with, following
+    /// `EndOfData`.
+    pub const EMPTY_STATUS_LIST: [u8; 3] = [START_LIST, 0x00, END_LIST];
+}
+
+/// Appends a tiny-atom encoded unsigned integer (0..=63) to `buf`.
+fn push_tiny_uint(buf: &mut Vec<u8>, value: u8) {
+    debug_assert!(value <= 0x3F);
+    buf.push(value & 0x3F);
+}
+
+/// Appends an atom-encoded byte string to `buf`, using a short atom (up
+/// to 15 bytes) or a medium atom (up to 2047 bytes) depending on length.
+fn push_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    if bytes.len() <= 15 {
+        buf.push(0b1010_0000 | bytes.len() as u8);
+    } else {
+        debug_assert!(
+            bytes.len() <= 0x7FF,
+            "byte string too long for a medium atom"
+        );
+        // Medium atom header: 110, Byte-string bit, Signed bit, 11-bit length.
+        let header = 0b1101_0000_0000_0000u16 | bytes.len() as u16;
+        buf.extend_from_slice(&header.to_be_bytes());
+    }
+    buf.extend_from_slice(bytes);
+}
+
+impl StorageSecurityCommand {
+    /// Performs TCG Level 0 Discovery and parses out the feature
+    /// descriptors needed to drive an Opal session.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Status::UNSUPPORTED`] if the device did not report an
+    /// Opal SSC or Pyrite SSC feature descriptor. See [`Self::receive_data`] for the
+    /// other errors that can occur.
+    pub fn opal_discovery(&mut self, media_id: u32, timeout: u64) -> Result<Discovery> {
+        let mut buf = vec![0u8; 512];
+        let data = self.receive_data_grow(
+            media_id,
+            timeout,
+            SECURITY_PROTOCOL_TCG,
+            LEVEL_0_DISCOVERY,
+            &mut buf,
+        )?;
+
+        parse_discovery(data)
+    }
+
+    /// Drives the full Opal unlock handshake: discovers the device's base
+    /// ComID, starts a session with the Locking SP authenticated as
+    /// `authority` using `password`, and clears the read/write lock on
+    /// `LockingRange0`.
+    ///
+    /// `timeout` is required because it's threaded through to every
+    /// underlying [`Self::send_data`]/[`Self::receive_data`] call, the same
+    /// as the rest of this protocol's API. `authority` is exposed because
+    /// the Locking SP accepts either `Admin1` or `User1`, and a caller
+    /// unlocking as the end-user PIN rather than the administrative one
+    /// needs to select that.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Status::ACCESS_DENIED`] if the device rejects the
+    /// `StartSession` (e.g. a wrong `password`) or the `LockingRange0.Set`
+    /// method call. See [`Self::opal_discovery`] and [`Self::send_data`] /
+    /// [`Self::receive_data`] for the other errors that can occur.
+    pub fn unlock(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        authority: Authority,
+        password: &[u8],
+    ) -> Result {
+        let discovery = self.opal_discovery(media_id, timeout)?;
+        let com_id = discovery.base_com_id;
+
+        // StartSession itself is sent on the control session (TSN = 0,
+        // HSN = 0); the TPer assigns the real session's TSN, which comes
+        // back in the response Packet header and must be used for every
+        // later packet in this SP session.
+        let start_session = build_start_session(authority, password);
+        self.send_method(media_id, timeout, com_id, 0, 0, &start_session)?;
+        let (tsn, response) = self.recv_method(media_id, timeout, com_id)?;
+        parse_method_status(&response)?;
+
+        let hsn = u32::from(HOST_SESSION_ID);
+
+        let clear_locks = build_clear_locking_range();
+        self.send_method(media_id, timeout, com_id, tsn, hsn, &clear_locks)?;
+        let (_, response) = self.recv_method(media_id, timeout, com_id)?;
+        parse_method_status(&response)?;
+
+        self.send_method(
+            media_id,
+            timeout,
+            com_id,
+            tsn,
+            hsn,
+            &[token::END_OF_SESSION],
+        )?;
+
+        Ok(())
+    }
+
+    /// Wraps `payload` in a ComPacket/Packet/SubPacket addressed to session
+    /// `(tsn, hsn)` and sends it over `com_id`.
+    fn send_method(
+        &mut self,
+        media_id: u32,
+        timeout: u64,
+        com_id: u16,
+        tsn: u32,
+        hsn: u32,
+        payload: &[u8],
+    ) -> Result {
+        let packet = wrap_com_packet(com_id, tsn, hsn, payload);
+        self.send_data(media_id, timeout, SECURITY_PROTOCOL_TCG, com_id, &packet)
+    }
+
+    /// Maximum number of times [`Self::recv_method`] will poll the device
+    /// for a completed response before giving up.
+    const RECV_METHOD_MAX_POLLS: u32 = 64;
+
+    /// Polls `com_id` until the device reports that a response is ready,
+    /// and returns the response Packet's TSN (the session number to use
+    /// for subsequent packets) along with the method's result token
+    /// stream, with the Packet/SubPacket framing stripped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Status::TIMEOUT`] if the device hasn't produced a
+    /// complete response after [`Self::RECV_METHOD_MAX_POLLS`] attempts.
+    fn recv_method(&mut self, media_id: u32, timeout: u64, com_id: u16) -> Result<(u32, Vec<u8>)> {
+        for _ in 0..Self::RECV_METHOD_MAX_POLLS {
+            let mut buf = vec![0u8; 512];
+            let data =
+                self.receive_data_grow(media_id, timeout, SECURITY_PROTOCOL_TCG, com_id, &mut buf)?;
+
+            if let Some((outstanding_data, packet)) = parse_com_packet(data) {
+                if outstanding_data == 0 && !packet.is_empty() {
+                    let (tsn, tokens) = parse_packet(packet).ok_or(Status::DEVICE_ERROR)?;
+                    return Ok((tsn, tokens.to_vec()));
+                }
+            }
+            // Outstanding data remains (or the packet was empty while the
+            // device is still working): poll again.
+        }
+
+        Err(Status::TIMEOUT.into())
+    }
+}
+
+/// Parses a Level 0 Discovery response into the subset of information
+/// needed to drive an Opal session.
+fn parse_discovery(data: &[u8]) -> Result<Discovery> {
+    if data.len() < DISCOVERY_HEADER_LEN {
+        return Err(Status::DEVICE_ERROR.into());
+    }
+
+    let mut base_com_id = None;
+    let mut locking_enabled = false;
+    let mut locked = false;
+
+    let mut pos = DISCOVERY_HEADER_LEN;
+    while pos + 4 <= data.len() {
+        let feature_code = u16::from_be_bytes([data[pos], data[pos + 1]]);
+        let length = data[pos + 3] as usize;
+        let payload_start = pos + 4;
+        let payload_end = payload_start + length;
+        if payload_end > data.len() {
+            break;
+        }
+        let payload = &data[payload_start..payload_end];
+
+        match feature_code {
+            FEATURE_CODE_OPAL_SSC | FEATURE_CODE_PYRITE_SSC_V1 | FEATURE_CODE_PYRITE_SSC_V2
+                if payload.len() >= 2 =>
+            {
+                base_com_id = Some(u16::from_be_bytes([payload[0], payload[1]]));
+            }
+            FEATURE_CODE_LOCKING if !payload.is_empty() => {
+                locking_enabled = payload[0] & LOCKING_ENABLED != 0;
+                locked = payload[0] & LOCKED != 0;
+            }
+            _ => {}
+        }
+
+        pos = payload_end;
+    }
+
+    let base_com_id = base_com_id.ok_or(Status::UNSUPPORTED)?;
+
+    Ok(Discovery {
+        base_com_id,
+        locking_enabled,
+        locked,
+    })
+}
+
+/// Builds the method call payload for `SMUID.StartSession` against the
+/// Locking SP, authenticated as `authority` with `password`.
+fn build_start_session(authority: Authority, password: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    buf.push(token::CALL);
+    push_bytes(&mut buf, &uid::SMUID);
+    push_bytes(&mut buf, &uid::START_SESSION);
+
+    buf.push(token::START_LIST);
+    push_tiny_uint(&mut buf, HOST_SESSION_ID);
+    push_bytes(&mut buf, &uid::LOCKING_SP);
+    push_tiny_uint(&mut buf, 1); // Write = TRUE.
+
+    // Optional parameters are passed as Named values: HostChallenge and
+    // HostSigningAuthority select the authority + PIN used to authenticate.
+    buf.push(0xF2); // StartName
+    push_tiny_uint(&mut buf, 0); // HostChallenge
+    push_bytes(&mut buf, password);
+    buf.push(0xF3); // EndName
+
+    buf.push(0xF2); // StartName
+    push_tiny_uint(&mut buf, 3); // HostSigningAuthority
+    push_bytes(&mut buf, &authority.uid());
+    buf.push(0xF3); // EndName
+
+    buf.push(token::END_LIST);
+    buf.push(token::END_OF_DATA);
+    buf.extend_from_slice(&token::EMPTY_STATUS_LIST);
+    buf
+}
+
+/// Builds the method call payload for `LockingRange0.Set`, clearing
+/// `ReadLockEnabled` and `WriteLockEnabled`.
+fn build_clear_locking_range() -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    buf.push(token::CALL);
+    push_bytes(&mut buf, &uid::LOCKING_RANGE0);
+    push_bytes(&mut buf, &uid::SET);
+
+    buf.push(token::START_LIST);
+    buf.push(0xF2); // StartName
+    push_tiny_uint(&mut buf, 1); // Values
+    buf.push(token::START_LIST);
+
+    buf.push(0xF2); // StartName
+    push_tiny_uint(&mut buf, 5); // ReadLockEnabled
+    push_tiny_uint(&mut buf, 0);
+    buf.push(0xF3); // EndName
+
+    buf.push(0xF2); // StartName
+    push_tiny_uint(&mut buf, 6); // WriteLockEnabled
+    push_tiny_uint(&mut buf, 0);
+    buf.push(0xF3); // EndName
+
+    buf.push(token::END_LIST);
+    buf.push(0xF3); // EndName
+    buf.push(token::END_LIST);
+    buf.push(token::END_OF_DATA);
+    buf.extend_from_slice(&token::EMPTY_STATUS_LIST);
+    buf
+}
+
+/// Wraps a method call `payload` in the ComPacket / Packet / SubPacket
+/// headers the TCG Storage spec requires for IF-SEND traffic, addressed to
+/// session `(tsn, hsn)`.
+///
+/// `tsn` and `hsn` are `0` for packets sent on the control session (i.e.
+/// `StartSession` itself); every later packet in an opened SP session must
+/// carry the TPer- and host-assigned session numbers instead, or a real
+/// TPer will reject the method call.
+fn wrap_com_packet(com_id: u16, tsn: u32, hsn: u32, payload: &[u8]) -> Vec<u8> {
+    // SubPacket: 6 reserved bytes, 2-byte Kind, 4-byte Length, then padding
+    // to a 4-byte boundary.
+    let mut sub_packet = vec![0u8; 6];
+    sub_packet.extend_from_slice(&0u16.to_be_bytes()); // Kind = data.
+    sub_packet.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    sub_packet.extend_from_slice(payload);
+    while sub_packet.len() % 4 != 0 {
+        sub_packet.push(0);
+    }
+
+    // Packet: TSN, HSN, SeqNumber, reserved, AckType, Acknowledgement,
+    // Length, then the SubPacket.
+    let mut packet = Vec::new();
+    packet.extend_from_slice(&tsn.to_be_bytes()); // TSN
+    packet.extend_from_slice(&hsn.to_be_bytes()); // HSN
+    packet.extend_from_slice(&0u32.to_be_bytes()); // SeqNumber
+    packet.extend_from_slice(&0u16.to_be_bytes()); // Reserved
+    packet.extend_from_slice(&0u16.to_be_bytes()); // AckType
+    packet.extend_from_slice(&0u32.to_be_bytes()); // Acknowledgement
+    packet.extend_from_slice(&(sub_packet.len() as u32).to_be_bytes());
+    packet.extend_from_slice(&sub_packet);
+
+    // ComPacket: Reserved, ComID, ComIDExtension, OutstandingData,
+    // MinTransfer, Length, then the Packet.
+    let mut com_packet = Vec::with_capacity(20 + packet.len());
+    com_packet.extend_from_slice(&0u32.to_be_bytes()); // Reserved.
+    com_packet.extend_from_slice(&com_id.to_be_bytes());
+    com_packet.extend_from_slice(&0u16.to_be_bytes()); // ComIDExtension.
+    com_packet.extend_from_slice(&0u32.to_be_bytes()); // OutstandingData.
+    com_packet.extend_from_slice(&0u32.to_be_bytes()); // MinTransfer.
+    com_packet.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+    com_packet.extend_from_slice(&packet);
+    com_packet
+}
+
+/// Parses a response ComPacket, returning the device's reported
+/// OutstandingData count along with the inner Packet bytes (including the
+/// nested Packet/SubPacket headers).
+///
+/// This mirrors the layout [`wrap_com_packet`] writes: `Reserved(4)`,
+/// `ComID(2)`, `ComIDExtension(2)`, `OutstandingData(4)`, `MinTransfer(4)`,
+/// `Length(4)`, then the Packet.
+fn parse_com_packet(data: &[u8]) -> Option<(u32, &[u8])> {
+    if data.len() < 20 {
+        return None;
+    }
+    let outstanding_data = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
+    let length = u32::from_be_bytes([data[16], data[17], data[18], data[19]]) as usize;
+    let payload = data.get(20..20 + length)?;
+    Some((outstanding_data, payload))
+}
+
+/// Strips the Packet and SubPacket framing from a `parse_com_packet` Packet
+/// payload, returning the Packet's TSN (the session number the TPer
+/// assigned, needed to address any further packet in this session) along
+/// with the method's token stream.
+///
+/// Layout: Packet = `TSN(4)`, `HSN(4)`, `SeqNumber(4)`, `Reserved(2)`,
+/// `AckType(2)`, `Acknowledgement(4)`, `Length(4)`, SubPacket; SubPacket =
+/// `Reserved(6)`, `Kind(2)`, `Length(4)`, payload. This mirrors the layout
+/// [`wrap_com_packet`] writes.
+fn parse_packet(packet: &[u8]) -> Option<(u32, &[u8])> {
+    if packet.len() < 24 {
+        return None;
+    }
+    let tsn = u32::from_be_bytes([packet[0], packet[1], packet[2], packet[3]]);
+
+    let sub_packet = packet.get(24..)?;
+    if sub_packet.len() < 12 {
+        return None;
+    }
+    let length =
+        u32::from_be_bytes([sub_packet[8], sub_packet[9], sub_packet[10], sub_packet[11]]) as usize;
+    let payload = sub_packet.get(12..12 + length)?;
+
+    Some((tsn, payload))
+}
+
+/// Checks a device's method result token stream (as returned by
+/// [`StorageSecurityCommand::recv_method`](StorageSecurityCommand) via
+/// `recv_method`) for a `SUCCESS` status, i.e. a method status list
+/// (`EndOfData, StartList, status code, EndList`) whose status code is `0`.
+///
+/// # Errors
+///
+/// Returns [`Status::DEVICE_ERROR`] if `tokens` doesn't end with a
+/// well-formed status list, or [`Status::ACCESS_DENIED`] if the status
+/// code is non-zero.
+fn parse_method_status(tokens: &[u8]) -> Result {
+    let end_of_data = tokens
+        .iter()
+        .rposition(|&b| b == token::END_OF_DATA)
+        .ok_or(Status::DEVICE_ERROR)?;
+    let status_list = &tokens[end_of_data + 1..];
+
+    let [start_list, status_code, end_list] = status_list else {
+        return Err(Status::DEVICE_ERROR.into());
+    };
+    if *start_list != token::START_LIST || *end_list != token::END_LIST {
+        return Err(Status::DEVICE_ERROR.into());
+    }
+
+    if *status_code != 0 {
+        return Err(Status::ACCESS_DENIED.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_tiny_uint_encodes_value_in_low_bits() {
+        let mut buf = Vec::new();
+        push_tiny_uint(&mut buf, 0x05);
+        assert_eq!(buf, [0x05]);
+    }
+
+    #[test]
+    fn push_bytes_short_atom() {
+        let mut buf = Vec::new();
+        push_bytes(&mut buf, &[0xAA, 0xBB, 0xCC]);
+        // Short atom header: 10, Byte-string bit, Signed bit, 4-bit length.
+        assert_eq!(buf, [0b1010_0011, 0xAA, 0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn push_bytes_medium_atom_sets_byte_string_bit() {
+        let bytes = [0u8; 20];
+        let mut buf = Vec::new();
+        push_bytes(&mut buf, &bytes);
+
+        // Medium atom header: 110, Byte-string bit set, Signed bit clear,
+        // 11-bit length. Before the fix this came out as 0xC0_14 (integer
+        // atom), not 0xD0_14 (byte-string atom).
+        assert_eq!(&buf[..2], &[0xD0, 0x14]);
+        assert_eq!(buf.len(), 2 + bytes.len());
+    }
+
+    #[test]
+    fn build_start_session_ends_with_empty_status_list() {
+        let buf = build_start_session(Authority::Admin1, b"password");
+        assert_eq!(buf[buf.len() - 4], token::END_OF_DATA);
+        assert_eq!(&buf[buf.len() - 3..], &token::EMPTY_STATUS_LIST);
+    }
+
+    #[test]
+    fn build_clear_locking_range_ends_with_empty_status_list() {
+        let buf = build_clear_locking_range();
+        assert_eq!(buf[buf.len() - 4], token::END_OF_DATA);
+        assert_eq!(&buf[buf.len() - 3..], &token::EMPTY_STATUS_LIST);
+    }
+
+    #[test]
+    fn wrap_and_parse_com_packet_round_trip() {
+        let payload = b"abc";
+        let wrapped = wrap_com_packet(0x0801, 7, 1, payload);
+
+        // ComID lands right after the 4-byte Reserved field.
+        assert_eq!(&wrapped[4..6], &0x0801u16.to_be_bytes());
+
+        let (outstanding_data, packet) =
+            parse_com_packet(&wrapped).expect("ComPacket should parse");
+        assert_eq!(outstanding_data, 0);
+
+        let (tsn, tokens) = parse_packet(packet).expect("Packet should parse");
+        assert_eq!(tsn, 7);
+        assert_eq!(tokens, payload);
+    }
+
+    #[test]
+    fn parse_com_packet_rejects_short_buffers() {
+        assert!(parse_com_packet(&[0u8; 19]).is_none());
+    }
+
+    #[test]
+    fn parse_packet_rejects_short_buffers() {
+        assert!(parse_packet(&[0u8; 23]).is_none());
+    }
+
+    #[test]
+    fn parse_discovery_reads_opal_and_locking_features() {
+        let mut data = vec![0u8; DISCOVERY_HEADER_LEN];
+
+        // Opal SSC feature descriptor: code, version/reserved, length, then
+        // a 4-byte payload whose first two bytes are the base ComID.
+        data.extend_from_slice(&FEATURE_CODE_OPAL_SSC.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x04]);
+        data.extend_from_slice(&0x0801u16.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x00]);
+
+        // Locking feature descriptor: LockingEnabled and Locked both set.
+        data.extend_from_slice(&FEATURE_CODE_LOCKING.to_be_bytes());
+        data.extend_from_slice(&[0x00, 0x01]);
+        data.push(LOCKING_ENABLED | LOCKED);
+
+        let discovery = parse_discovery(&data).expect("discovery should parse");
+        assert_eq!(discovery.base_com_id, 0x0801);
+        assert!(discovery.locking_enabled);
+        assert!(discovery.locked);
+    }
+
+    #[test]
+    fn parse_discovery_rejects_short_buffers() {
+        assert!(parse_discovery(&[0u8; DISCOVERY_HEADER_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn parse_discovery_requires_opal_ssc_feature() {
+        let data = vec![0u8; DISCOVERY_HEADER_LEN];
+        assert!(parse_discovery(&data).is_err());
+    }
+
+    #[test]
+    fn parse_method_status_accepts_success() {
+        let tokens = [
+            0xAA,
+            token::END_OF_DATA,
+            token::START_LIST,
+            0x00,
+            token::END_LIST,
+        ];
+        assert!(parse_method_status(&tokens).is_ok());
+    }
+
+    #[test]
+    fn parse_method_status_rejects_non_success() {
+        let tokens = [token::END_OF_DATA, token::START_LIST, 0x01, token::END_LIST];
+        assert!(parse_method_status(&tokens).is_err());
+    }
+
+    #[test]
+    fn parse_method_status_rejects_missing_status_list() {
+        assert!(parse_method_status(&[token::END_OF_DATA]).is_err());
+        assert!(parse_method_status(&[0xAA]).is_err());
+    }
+}